@@ -0,0 +1,244 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::blocks::blockchain::Blockchain;
+use crate::transactions::transaction::Transaction;
+use crate::utils::{serialize, Storage};
+
+/// 默认内存池容量（交易条数）
+const DEFAULT_CAPACITY: usize = 5000;
+/// 费率放大系数，避免浮点比较：fee_rate = fee * SCALE / size
+const FEE_RATE_SCALE: i64 = 1000;
+
+/// 费率：以手续费除以交易序列化字节长度衡量，整数放大后可直接比较
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FeeRate(i64);
+
+impl FeeRate {
+    fn new(fee: i32, size: usize) -> Self {
+        if size == 0 {
+            return FeeRate(0);
+        }
+        FeeRate(fee as i64 * FEE_RATE_SCALE / size as i64)
+    }
+}
+
+/// 交易内存池：缓冲已验证但未上链的交易，按费率排序，满时淘汰费率最低者
+pub struct Mempool {
+    capacity: usize,
+    // 费率 -> 交易 id 列表，便于快速取最优/淘汰最差
+    by_fee_rate: BTreeMap<FeeRate, Vec<String>>,
+    txs: HashMap<String, Transaction>,
+    rates: HashMap<String, FeeRate>,
+    // 已被池内交易占用的 outpoint，用于拒绝双花
+    claimed: HashSet<(String, usize)>,
+}
+
+impl Mempool {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Mempool {
+            capacity,
+            by_fee_rate: BTreeMap::new(),
+            txs: HashMap::new(),
+            rates: HashMap::new(),
+            claimed: HashSet::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.txs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.txs.is_empty()
+    }
+
+    /// 向内存池加入交易：先验证签名，再拒绝与池内交易冲突的双花，满载时淘汰最低费率。
+    /// `verify` 只检查签名、不检查 outpoint 是否真实存在，所以 `fee` 可能因为引用了
+    /// 不存在的前序交易/输出而失败——这种交易是非法的，直接拒绝而不是让它终止进程。
+    pub fn add<T: Storage>(&mut self, tx: Transaction, bc: &Blockchain<T>) -> bool {
+        if !tx.verify(bc) {
+            return false;
+        }
+        for vin in tx.get_vin() {
+            let outpoint = (vin.get_txid(), vin.get_vout());
+            if self.claimed.contains(&outpoint) {
+                return false;
+            }
+        }
+
+        let fee = match tx.fee(bc) {
+            Some(fee) => fee,
+            None => return false,
+        };
+        let size = serialize(&tx).map(|s| s.len()).unwrap_or(0);
+        let rate = FeeRate::new(fee, size);
+
+        if self.txs.len() >= self.capacity && !self.evict_lowest(rate) {
+            // 新交易费率不高于池中最低者，直接拒绝
+            return false;
+        }
+
+        let txid = tx.get_id();
+        for vin in tx.get_vin() {
+            self.claimed.insert((vin.get_txid(), vin.get_vout()));
+        }
+        self.by_fee_rate.entry(rate).or_default().push(txid.clone());
+        self.rates.insert(txid.clone(), rate);
+        self.txs.insert(txid, tx);
+        true
+    }
+
+    /// 按费率从高到低取出交易填充区块，直至达到数量或字节上限，其余留在池中。
+    /// 一旦剩余字节预算装不下当前交易就停止，而不是跳过它继续装后面费率更低的交易——
+    /// 否则就破坏了"高费率优先"的承诺。
+    pub fn collect(&mut self, max_count: usize, max_bytes: usize) -> Vec<Transaction> {
+        let mut picked = vec![];
+        let mut bytes = 0;
+        let ids: Vec<String> = self
+            .by_fee_rate
+            .iter()
+            .rev()
+            .flat_map(|(_, ids)| ids.iter().cloned())
+            .collect();
+
+        for txid in ids {
+            if picked.len() >= max_count {
+                break;
+            }
+            if let Some(tx) = self.txs.get(&txid) {
+                let size = serialize(tx).map(|s| s.len()).unwrap_or(0);
+                if bytes + size > max_bytes {
+                    break;
+                }
+                bytes += size;
+                picked.push(txid.clone());
+            }
+        }
+
+        picked
+            .into_iter()
+            .filter_map(|txid| self.remove(&txid))
+            .collect()
+    }
+
+    /// 淘汰费率最低的交易；若新交易费率不高于最低者则不淘汰，返回 false
+    fn evict_lowest(&mut self, incoming: FeeRate) -> bool {
+        let lowest = match self.by_fee_rate.keys().next().copied() {
+            Some(rate) => rate,
+            None => return true,
+        };
+        if incoming <= lowest {
+            return false;
+        }
+        if let Some(ids) = self.by_fee_rate.get(&lowest) {
+            if let Some(txid) = ids.first().cloned() {
+                self.remove(&txid);
+                return true;
+            }
+        }
+        true
+    }
+
+    fn remove(&mut self, txid: &str) -> Option<Transaction> {
+        let tx = self.txs.remove(txid)?;
+        if let Some(rate) = self.rates.remove(txid) {
+            if let Some(ids) = self.by_fee_rate.get_mut(&rate) {
+                ids.retain(|id| id != txid);
+                if ids.is_empty() {
+                    self.by_fee_rate.remove(&rate);
+                }
+            }
+        }
+        for vin in tx.get_vin() {
+            self.claimed.remove(&(vin.get_txid(), vin.get_vout()));
+        }
+        Some(tx)
+    }
+}
+
+impl Default for Mempool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::current_dir;
+    use std::sync::Arc;
+
+    use crate::blocks::blockchain::Blockchain;
+    use crate::transactions::mempool::Mempool;
+    use crate::transactions::{Transaction, UTXOSet};
+    use crate::utils::SledDb;
+    use crate::wallets::wallets::Wallets;
+
+    #[test]
+    fn test_mempool_rejects_double_spend() {
+        let justin_addr = "1M684nX5dTNQYi2ELSCazjyz5dgegJ3mVD";
+
+        let mut wallets = Wallets::new().unwrap();
+        let alice_addr = wallets.create_wallet();
+
+        let path = current_dir().unwrap().join("data");
+        let storage = Arc::new(SledDb::new(path));
+
+        let bc = Blockchain::new(storage.clone(), justin_addr);
+        let utxos = UTXOSet::new(storage);
+        utxos.reindex(&bc).unwrap();
+
+        let tx = Transaction::new_utxo(justin_addr, &alice_addr, 4, &utxos, &bc);
+
+        let mut pool = Mempool::new();
+        assert!(pool.add(tx.clone(), &bc));
+        assert_eq!(pool.len(), 1);
+
+        // 同一笔交易复用同一批 outpoint 再次提交，应当被当作双花拒绝
+        assert!(!pool.add(tx, &bc));
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn test_mempool_evicts_lowest_fee_rate_when_full() {
+        let justin_addr = "1M684nX5dTNQYi2ELSCazjyz5dgegJ3mVD";
+
+        let mut wallets = Wallets::new().unwrap();
+        let alice_addr = wallets.create_wallet();
+        let bob_addr = wallets.create_wallet();
+
+        let path = current_dir().unwrap().join("data");
+        let storage = Arc::new(SledDb::new(path));
+
+        let mut bc = Blockchain::new(storage.clone(), justin_addr);
+        let utxos = UTXOSet::new(storage);
+        utxos.reindex(&bc).unwrap();
+
+        // 先给 alice、bob 各自转一笔钱，上链后二者各自拥有独立、互不冲突的 UTXO
+        let fund_alice = Transaction::new_utxo(justin_addr, &alice_addr, 10, &utxos, &bc);
+        let fund_bob = Transaction::new_utxo(justin_addr, &bob_addr, 10, &utxos, &bc);
+        bc.mine_block(&[fund_alice, fund_bob]);
+        utxos.reindex(&bc).unwrap();
+
+        // 零手续费的普通转账
+        let tx_zero_fee = Transaction::new_utxo(&alice_addr, justin_addr, 1, &utxos, &bc);
+        // 携带数据的交易固定收取 DATA_TX_FEE 手续费，费率高于零手续费交易
+        let tx_with_fee = Transaction::new_data_tx(&bob_addr, b"memo", &utxos, &bc);
+        let fee_txid = tx_with_fee.get_id();
+
+        let mut pool = Mempool::with_capacity(1);
+        assert!(pool.add(tx_zero_fee, &bc));
+        assert_eq!(pool.len(), 1);
+
+        // 池已满：新交易费率更高，应当淘汰池中零手续费的交易
+        assert!(pool.add(tx_with_fee, &bc));
+        assert_eq!(pool.len(), 1);
+        assert!(pool
+            .collect(10, usize::MAX)
+            .iter()
+            .any(|tx| tx.get_id() == fee_txid));
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Serialize, Deserialize};
 
-use crate::{ utils::{serialize, hash_to_str, ecdsa_p256_sha256_sign_digest, ecdsa_p256_sha256_sign_verify}};
+use crate::{ utils::{hash_to_str, ecdsa_p256_sha256_sign_digest, ecdsa_p256_sha256_sign_verify}};
 use crate::blocks::blockchain::Blockchain;
 use crate::transactions::tx_input::Txinput;
 use crate::transactions::tx_output::Txoutput;
@@ -10,6 +10,8 @@ use crate::utils::{hash_pub_key, Storage};
 use crate::wallets::wallets::Wallets;
 
 const SUBSIDY: i32= 10;
+/// 数据交易支付的固定手续费
+const DATA_TX_FEE: i32 = 1;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Transaction {
@@ -19,9 +21,9 @@ pub struct Transaction {
 }
 
 impl Transaction {
-    pub fn new_coinbase(to: &str) -> Self {
+    pub fn new_coinbase(to: &str, total_fees: i32) -> Self {
         let txin = Txinput::default();
-        let txout = Txoutput::new(SUBSIDY, to);
+        let txout = Txoutput::new(SUBSIDY + total_fees, to);
 
         let mut tx = Transaction {
             id: String::new(),
@@ -67,56 +69,92 @@ impl Transaction {
         tx
     }
 
-    fn set_hash(&mut self) {
-        if let Ok(tx_ser) = serialize(self) {
-            self.id = hash_to_str(&tx_ser)
-        }
-    }
+    /// 构造一笔携带任意数据的交易（类似 OP_RETURN）：花费一笔最小输入支付手续费，
+    /// 并写入一个零值、无锁定脚本的数据输出，承载应用层载荷。
+    pub fn new_data_tx<T: Storage>(from: &str, payload: &[u8], utxo_set: &UTXOSet<T>, bc: &Blockchain<T>) -> Self {
+        let wallets = Wallets::new().unwrap();
+        let wallet = wallets.get_wallet(from).unwrap();
+        let public_key_hash = hash_pub_key(wallet.get_public_key());
 
-    fn sign<T: Storage>(&mut self, bc: &Blockchain<T>, pkcs8: &[u8]) {
-        let mut tx_copy = self.trimmed_copy();
+        let (accumulated, valid_outputs) = utxo_set.find_spendable_outputs(&public_key_hash, DATA_TX_FEE);
+        if accumulated < DATA_TX_FEE {
+            panic!("Error not enough funds");
+        }
 
-        for (idx, vin) in self.vin.iter_mut().enumerate() {
-            // 查找输入引用的交易
-            let prev_tx_option = bc.find_transaction(vin.get_txid());
-            if prev_tx_option.is_none() {
-                panic!("ERROR: Previous transaction is not correct")
+        let mut inputs = vec![];
+        for (txid, outputs) in valid_outputs {
+            for idx in outputs {
+                let input = Txinput::new(txid.clone(), idx.clone(), wallet.get_public_key().to_vec());
+                inputs.push(input);
             }
-            let prev_tx = prev_tx_option.unwrap();
-            tx_copy.vin[idx].set_signature(vec![]);
-            tx_copy.vin[idx].set_pub_key(prev_tx.vout[vin.get_vout()].get_pub_key_hash());
-            tx_copy.set_hash();
+        }
+
+        let mut outputs = vec![Txoutput::new_data(payload)];
+        if accumulated > DATA_TX_FEE {
+            outputs.push(Txoutput::new(accumulated - DATA_TX_FEE, &from));
+        }
+
+        let mut tx = Transaction {
+            id: String::new(),
+            vin: inputs,
+            vout: outputs,
+        };
+        tx.set_hash();
+        tx.sign(bc, wallet.get_pkcs8());
 
-            tx_copy.vin[idx].set_pub_key(&vec![]);
+        tx
+    }
 
-            // 使用私钥对数据签名
-            let signature = ecdsa_p256_sha256_sign_digest(pkcs8, tx_copy.id.as_bytes());
+    /// 构造用于签名/验签的紧凑规范编码：只承诺共识相关字段——每个输入的 outpoint
+    /// （txid + vout 序号）以及每个输出的金额与公钥哈希，整数字段一律以小端编码。
+    /// 逐输入的 ECDSA 签名（见证数据）不参与该摘要，因此 `id` 不再随签名变化。
+    ///
+    /// `vin` 记录是定长的，但 `vout` 记录因公钥哈希长度而变长，且两节之间没有任何
+    /// 分隔符；因此必须显式写入各节的元素个数，否则形状不同的交易可能拼出同样的字节
+    /// 序列，摘要就不再是无歧义的。
+    fn signing_payload(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        // 见证之外的输入部分：元素个数 + 逐个 outpoint
+        buf.extend_from_slice(&(self.vin.len() as u32).to_le_bytes());
+        for vin in &self.vin {
+            buf.extend_from_slice(vin.get_txid().as_bytes());
+            buf.extend_from_slice(&(vin.get_vout() as u32).to_le_bytes());
+        }
+        // 输出部分：元素个数 + 逐个（金额 + 公钥哈希）
+        buf.extend_from_slice(&(self.vout.len() as u32).to_le_bytes());
+        for out in &self.vout {
+            buf.extend_from_slice(&out.get_value().to_le_bytes());
+            let pub_key_hash = out.get_pub_key_hash();
+            buf.extend_from_slice(&(pub_key_hash.len() as u32).to_le_bytes());
+            buf.extend_from_slice(&pub_key_hash);
+        }
+        buf
+    }
+
+    fn set_hash(&mut self) {
+        self.id = hash_to_str(&self.signing_payload());
+    }
+
+    fn sign<T: Storage>(&mut self, _bc: &Blockchain<T>, pkcs8: &[u8]) {
+        // 摘要只覆盖 outpoint 与输出，不含任何签名，故一次计算即可用于所有输入
+        let digest = self.signing_payload();
+        for vin in self.vin.iter_mut() {
+            let signature = ecdsa_p256_sha256_sign_digest(pkcs8, &digest);
             vin.set_signature(signature);
         }
     }
 
-    pub fn verify<T: Storage>(&self, bc: &Blockchain<T>) -> bool {
+    pub fn verify<T: Storage>(&self, _bc: &Blockchain<T>) -> bool {
         if self.is_coinbase() {
             return true;
         }
-        let mut tx_copy = self.trimmed_copy();
-        for (idx, vin) in self.vin.iter().enumerate() {
-            let prev_tx_option = bc.find_transaction(vin.get_txid());
-            if prev_tx_option.is_none() {
-                panic!("ERROR: Previous transaction is not correct")
-            }
-            let prev_tx = prev_tx_option.unwrap();
-            tx_copy.vin[idx].set_signature(vec![]);
-            tx_copy.vin[idx].set_pub_key(prev_tx.vout[vin.get_vout()].get_pub_key_hash());
-            tx_copy.set_hash();
-
-            tx_copy.vin[idx].set_pub_key(&vec![]);
-
-            // 使用公钥验证签名
+        let digest = self.signing_payload();
+        for vin in &self.vin {
+            // 见证数据（签名）单独存放，逐输入对同一摘要验签
             let verify = ecdsa_p256_sha256_sign_verify(
                 vin.get_pub_key(),
                 vin.get_signature(),
-                tx_copy.id.as_bytes(),
+                &digest,
             );
             if !verify {
                 return false;
@@ -125,26 +163,30 @@ impl Transaction {
         true
     }
 
-    /// 判断是否是 coinbase 交易
-    pub fn is_coinbase(&self) -> bool {
-        self.vin.len() == 1 && self.vin[0].get_pub_key().len() == 0
-    }
-
-    fn trimmed_copy(&self) -> Transaction {
-        let mut inputs = vec![];
-        let mut outputs = vec![];
-        for input in &self.vin {
-            let txinput = Txinput::new(input.get_txid(), input.get_vout(), vec![]);
-            inputs.push(txinput);
+    /// 计算交易手续费：输入引用的输出值之和减去本交易输出值之和。
+    /// 输入引用了不存在的交易/越界的输出，或手续费为负，均视为非法交易，返回 `None`
+    /// 而不是 panic —— `vin` 由外部提交，`verify` 只检查签名，不保证 outpoint 真实存在。
+    pub fn fee<T: Storage>(&self, bc: &Blockchain<T>) -> Option<i32> {
+        if self.is_coinbase() {
+            return Some(0);
         }
-        for output in &self.vout {
-            outputs.push(output.clone());
+        let mut inputs_sum = 0;
+        for vin in &self.vin {
+            let prev_tx = bc.find_transaction(vin.get_txid())?;
+            let prev_out = prev_tx.vout.get(vin.get_vout())?;
+            inputs_sum += prev_out.get_value();
         }
-        Transaction {
-            id: self.id.clone(),
-            vin: inputs,
-            vout: outputs,
+        let outputs_sum: i32 = self.vout.iter().map(|out| out.get_value()).sum();
+        let fee = inputs_sum - outputs_sum;
+        if fee < 0 {
+            return None;
         }
+        Some(fee)
+    }
+
+    /// 判断是否是 coinbase 交易
+    pub fn is_coinbase(&self) -> bool {
+        self.vin.len() == 1 && self.vin[0].get_pub_key().len() == 0
     }
 
     pub fn get_id(&self) -> String {
@@ -158,8 +200,31 @@ impl Transaction {
     pub fn get_vin(&self) -> &[Txinput] {
         self.vin.as_slice()
     }
+
+    /// 测试专用：直接从给定的 vin/vout 构造交易，绕过钱包与 UTXO 查找，
+    /// 便于构造越界/损坏的 vin 来验证下游读取路径的健壮性。
+    #[cfg(test)]
+    pub(crate) fn new_for_test(vin: Vec<Txinput>, vout: Vec<Txoutput>) -> Self {
+        let mut tx = Transaction {
+            id: String::new(),
+            vin,
+            vout,
+        };
+        tx.set_hash();
+        tx
+    }
 }
 
+/// 汇总一组交易的手续费总和，结果可作为 `Transaction::new_coinbase` 的
+/// `total_fees` 参数。`Blockchain::mine_block` 不调用本函数。任何一笔交易的
+/// 手续费算不出来（越界/不存在的 vin）都视为该批交易非法，整体返回 `None`。
+pub fn total_fees<T: Storage>(bc: &Blockchain<T>, txs: &[Transaction]) -> Option<i32> {
+    let mut total = 0;
+    for tx in txs {
+        total += tx.fee(bc)?;
+    }
+    Some(total)
+}
 
 #[cfg(test)]
 mod tests {
@@ -167,6 +232,7 @@ mod tests {
     use std::sync::Arc;
     use crate::blocks::blockchain::Blockchain;
     use crate::transactions::{Transaction, UTXOSet};
+    use crate::transactions::transaction::{total_fees, DATA_TX_FEE};
     use crate::utils::SledDb;
     use crate::wallets::wallets::Wallets;
 
@@ -189,6 +255,10 @@ mod tests {
         let tx_1 = Transaction::new_utxo(justin_addr, &bob_addr, 4, &utxos, &bc);
         let tx_2 = Transaction::new_utxo(justin_addr, &bruce_addr, 2, &utxos, &bc);
 
+        // 签名覆盖的是规范编码后的摘要，验签必须能通过
+        assert!(tx_1.verify(&bc));
+        assert!(tx_2.verify(&bc));
+
         let txs = vec![tx_1, tx_2];
 
         bc.mine_block(&txs);
@@ -196,5 +266,48 @@ mod tests {
 
         bc.blocks_info();
     }
+
+    /// 摘要需以 vin/vout 各自的数量开头，消除不同形状交易之间的边界歧义
+    #[test]
+    fn test_signing_payload_prefixes_section_lengths() {
+        let coinbase = Transaction::new_coinbase("1M684nX5dTNQYi2ELSCazjyz5dgegJ3mVD", 0);
+        let payload = coinbase.signing_payload();
+
+        assert_eq!(&payload[0..4], &(coinbase.vin.len() as u32).to_le_bytes()[..]);
+
+        // 输入节之后紧跟输出节的元素个数
+        let vout_at = 4 + coinbase.vin.iter().map(|vin| vin.get_txid().len() + 4).sum::<usize>();
+        assert_eq!(&payload[vout_at..vout_at + 4], &(coinbase.vout.len() as u32).to_le_bytes()[..]);
+    }
+
+    /// total_fees 应当汇总所有非 coinbase 交易的手续费
+    #[test]
+    fn test_total_fees_sums_non_coinbase_transactions() {
+        let justin_addr = "1M684nX5dTNQYi2ELSCazjyz5dgegJ3mVD";
+
+        let mut wallets = Wallets::new().unwrap();
+        let alice_addr = wallets.create_wallet();
+        let bob_addr = wallets.create_wallet();
+
+        let path = current_dir().unwrap().join("data");
+        let storage = Arc::new(SledDb::new(path));
+
+        let mut bc = Blockchain::new(storage.clone(), justin_addr);
+        let utxos = UTXOSet::new(storage);
+        utxos.reindex(&bc).unwrap();
+
+        let fund_alice = Transaction::new_utxo(justin_addr, &alice_addr, 10, &utxos, &bc);
+        let fund_bob = Transaction::new_utxo(justin_addr, &bob_addr, 10, &utxos, &bc);
+        bc.mine_block(&[fund_alice, fund_bob]);
+        utxos.reindex(&bc).unwrap();
+
+        // 零手续费的普通转账
+        let tx_zero_fee = Transaction::new_utxo(&alice_addr, justin_addr, 1, &utxos, &bc);
+        // 携带数据的交易固定收取 DATA_TX_FEE 手续费
+        let tx_with_fee = Transaction::new_data_tx(&bob_addr, b"memo", &utxos, &bc);
+
+        let fees = total_fees(&bc, &[tx_zero_fee, tx_with_fee]).unwrap();
+        assert_eq!(fees, DATA_TX_FEE);
+    }
 }
 
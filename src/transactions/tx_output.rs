@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::base58_decode;
+
+/// 输出种类：显式区分普通支付输出与数据输出，在构造时就定下来，不能靠金额推断——
+/// 合法的零金额输出（例如未来的零手续费 coinbase，或金额为 0 的转账）不该被误判为
+/// 数据输出，进而被 `UTXOSet::reindex` 悄悄丢出索引、凭空丢钱。
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TxoutputKind {
+    Payment,
+    Data,
+}
+
+impl Default for TxoutputKind {
+    fn default() -> Self {
+        TxoutputKind::Payment
+    }
+}
+
+/// 交易输出：要么锁定到某个地址对应的公钥哈希、承载可花费金额，
+/// 要么是无锁定脚本的数据输出，只用于携带应用层载荷（类似 OP_RETURN）。
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Txoutput {
+    value: i32,
+    pub_key_hash: Vec<u8>,
+    index: usize,
+    kind: TxoutputKind,
+}
+
+impl Txoutput {
+    pub fn new(value: i32, address: &str) -> Self {
+        let mut txout = Txoutput {
+            value,
+            pub_key_hash: vec![],
+            index: 0,
+            kind: TxoutputKind::Payment,
+        };
+        txout.lock(address);
+        txout
+    }
+
+    /// 构造一个零值、无锁定脚本的数据输出：不代表可花费资金，只承载任意载荷
+    pub fn new_data(payload: &[u8]) -> Self {
+        Txoutput {
+            value: 0,
+            pub_key_hash: payload.to_vec(),
+            index: 0,
+            kind: TxoutputKind::Data,
+        }
+    }
+
+    fn lock(&mut self, address: &str) {
+        let payload = base58_decode(address);
+        self.pub_key_hash = payload[1..payload.len() - 4].to_vec();
+    }
+
+    /// 是否是数据输出，由构造时定下的 `kind` 决定，不依赖金额是否为零
+    pub fn is_data(&self) -> bool {
+        self.kind == TxoutputKind::Data
+    }
+
+    pub fn get_value(&self) -> i32 {
+        self.value
+    }
+
+    pub fn get_pub_key_hash(&self) -> &[u8] {
+        &self.pub_key_hash
+    }
+
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    pub fn set_index(&mut self, index: usize) {
+        self.index = index;
+    }
+}
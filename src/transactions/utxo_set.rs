@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::blocks::blockchain::Blockchain;
+use crate::transactions::tx_output::Txoutput;
+use crate::utils::{deserialize, serialize, Storage};
+
+const UTXO_KEY_PREFIX: &str = "utxo-";
+
+/// 未花费交易输出集合：按交易 id 索引当前未花费的输出，持久化在 `db`（sled）中，
+/// 避免每次花费都要遍历整条链重新计算。`chainstate` 是同一份数据在内存中的缓存，
+/// 专门供 `find_spendable_outputs` 按公钥哈希扫描；写入始终落到 `db`，单笔查询
+/// （`get_outputs`/`get_utxo`）也直接命中 `db`，不依赖这份缓存是否还在。
+pub struct UTXOSet<T: Storage> {
+    db: Arc<T>,
+    chainstate: Mutex<HashMap<String, Vec<Txoutput>>>,
+}
+
+impl<T: Storage> UTXOSet<T> {
+    pub fn new(db: Arc<T>) -> Self {
+        UTXOSet {
+            db,
+            chainstate: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn utxo_key(txid: &str) -> String {
+        format!("{}{}", UTXO_KEY_PREFIX, txid)
+    }
+
+    /// 重建索引：遍历整条链，为每笔交易记录仍未被花费的输出，写入 `db` 并刷新内存缓存。
+    /// 数据输出（`Txoutput::is_data`）不代表可花费资金，建索引时直接跳过，既不会被当成
+    /// 找零计入余额，也不会出现在 `find_spendable_outputs` 的候选里。
+    pub fn reindex(&self, bc: &Blockchain<T>) -> Result<(), failure::Error> {
+        let mut spent: HashMap<String, Vec<usize>> = HashMap::new();
+        for block in bc.iter() {
+            for tx in block.get_transactions() {
+                if tx.is_coinbase() {
+                    continue;
+                }
+                for vin in tx.get_vin() {
+                    spent.entry(vin.get_txid()).or_default().push(vin.get_vout());
+                }
+            }
+        }
+
+        let mut chainstate = HashMap::new();
+        for block in bc.iter() {
+            for tx in block.get_transactions() {
+                let txid = tx.get_id();
+                let spent_indices = spent.get(&txid);
+                let outputs: Vec<Txoutput> = tx
+                    .get_vout()
+                    .iter()
+                    .enumerate()
+                    .filter(|(idx, out)| {
+                        !out.is_data() && spent_indices.map_or(true, |s| !s.contains(idx))
+                    })
+                    .map(|(idx, out)| {
+                        let mut out = out.clone();
+                        out.set_index(idx);
+                        out
+                    })
+                    .collect();
+
+                // 已全部花费的交易写入空列表，覆盖上次重建留下的旧记录
+                self.db
+                    .set(Self::utxo_key(&txid).as_bytes(), serialize(&outputs)?)?;
+                if !outputs.is_empty() {
+                    chainstate.insert(txid, outputs);
+                }
+            }
+        }
+
+        *self.chainstate.lock().unwrap() = chainstate;
+        Ok(())
+    }
+
+    /// 查询某笔交易当前记录在索引中的未花费输出，直接命中 `db`，无需遍历整条链
+    pub fn get_outputs(&self, txid: &str) -> Option<Vec<Txoutput>> {
+        let bytes = self.db.get(Self::utxo_key(txid).as_bytes()).ok()??;
+        deserialize(&bytes).ok()
+    }
+
+    /// 按 outpoint `(txid, vout_index)` 查询其对应的未花费输出，已花费或不存在时返回 `None`。
+    ///
+    /// 直接命中 `db` 中 sled 索引的 UTXO 存储，无需遍历整条链，是手续费计算、支付证明
+    /// 以及未来 RPC 层所依赖的基础原语。对应全节点暴露的 `get_utxo(outpoint)`。
+    pub fn get_utxo(&self, txid: &str, vout_index: usize) -> Option<Txoutput> {
+        self.get_outputs(txid)
+            .and_then(|outputs| outputs.into_iter().find(|out| out.get_index() == vout_index))
+    }
+
+    /// 累加指定公钥哈希名下的可花费输出，直至达到所需金额。
+    /// 数据输出不代表可花费资金，已在 `reindex` 时被排除，这里天然不会被选中。
+    pub fn find_spendable_outputs(
+        &self,
+        pub_key_hash: &[u8],
+        amount: i32,
+    ) -> (i32, HashMap<String, Vec<usize>>) {
+        let mut unspent_outputs: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut accumulated = 0;
+
+        let chainstate = self.chainstate.lock().unwrap();
+        'outer: for (txid, outputs) in chainstate.iter() {
+            for out in outputs {
+                if accumulated >= amount {
+                    break 'outer;
+                }
+                if out.get_pub_key_hash() == pub_key_hash {
+                    accumulated += out.get_value();
+                    unspent_outputs
+                        .entry(txid.clone())
+                        .or_default()
+                        .push(out.get_index());
+                }
+            }
+        }
+
+        (accumulated, unspent_outputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::current_dir;
+    use std::sync::Arc;
+
+    use crate::blocks::blockchain::Blockchain;
+    use crate::transactions::utxo_set::UTXOSet;
+    use crate::utils::SledDb;
+
+    #[test]
+    fn test_get_utxo_roundtrip() {
+        let justin_addr = "1M684nX5dTNQYi2ELSCazjyz5dgegJ3mVD";
+
+        let path = current_dir().unwrap().join("data");
+        let storage = Arc::new(SledDb::new(path));
+
+        let bc = Blockchain::new(storage.clone(), justin_addr);
+        let utxos = UTXOSet::new(storage);
+        utxos.reindex(&bc).unwrap();
+
+        let coinbase_tx = bc.iter().next().unwrap().get_transactions()[0].clone();
+
+        let found = utxos.get_utxo(&coinbase_tx.get_id(), 0);
+        assert_eq!(
+            found.map(|out| out.get_value()),
+            Some(coinbase_tx.get_vout()[0].get_value())
+        );
+
+        // 不存在的 vout 序号应返回 None 而不是 panic
+        assert!(utxos.get_utxo(&coinbase_tx.get_id(), 99).is_none());
+    }
+}